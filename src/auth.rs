@@ -0,0 +1,52 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+
+/// An Argon2 PHC-format password hash (e.g. `$argon2id$v=19$...$...`), salted
+/// per user. The raw password is never stored.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordHashRecord(String);
+
+impl PasswordHashRecord {
+    /// Wraps an already-hashed PHC string, e.g. one read back from storage.
+    pub fn from_phc_string(hash: String) -> Self {
+        Self(hash)
+    }
+
+    pub fn hash(password: &str) -> Result<Self, anyhow::Error> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| anyhow::anyhow!("failed to hash password: {e}"))?;
+        Ok(Self(hash.to_string()))
+    }
+
+    /// Verifies `password` against this hash in constant time.
+    pub fn verify(&self, password: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(&self.0) else {
+            return false;
+        };
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A registered user, persisted in the same storage backend as tickets.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    pub username: String,
+    pub password_hash: PasswordHashRecord,
+}
+
+/// Credentials presented by a client when authenticating.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
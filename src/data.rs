@@ -1,44 +1,181 @@
 use std::convert::TryFrom;
 use std::collections::BTreeMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use serde::{Serialize, Deserialize};
 
-#[derive(Clone)]
+use crate::auth::{PasswordHashRecord, User};
+use crate::storage::Storage;
+
+/// How many unconsumed [`TicketEvent`]s a subscriber can fall behind by
+/// before it starts missing them.
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Tickets plus a write-through cache in front of whichever [`Storage`]
+/// backend is configured, so hot tickets stay fast while the backend is the
+/// source of truth that survives a restart.
 pub struct TicketStore {
-    tickets: BTreeMap<TicketId, Arc<RwLock<Ticket>>>,
-    counter: u64,
+    backend: Arc<dyn Storage>,
+    cache: RwLock<BTreeMap<TicketId, Arc<RwLock<Ticket>>>>,
+    events: broadcast::Sender<TicketEvent>,
 }
 
 impl TicketStore {
-    pub fn new() -> Self {
+    pub fn new(backend: Arc<dyn Storage>) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
         Self {
-            tickets: BTreeMap::new(),
-            counter: 0,
+            backend,
+            cache: RwLock::new(BTreeMap::new()),
+            events,
         }
     }
 
-    pub fn add_ticket(&mut self, ticket: TicketDraft) -> TicketId {
-        let id = TicketId(self.counter);
-        self.counter += 1;
+    /// Subscribes to the stream of ticket creation/update events. Callers
+    /// filter by [`Status`] themselves, same as the `Subscribe` command.
+    pub fn subscribe(&self) -> broadcast::Receiver<TicketEvent> {
+        self.events.subscribe()
+    }
+
+    /// Registers a new user, hashing `password` with Argon2 before it is
+    /// persisted.
+    pub async fn create_user(&self, username: String, password: &str) -> Result<(), anyhow::Error> {
+        let password_hash = PasswordHashRecord::hash(password)?;
+        self.backend.create_user(User { username, password_hash }).await
+    }
+
+    /// Verifies a username/password pair against the stored, hashed
+    /// credentials. Returns `false` for an unknown user rather than
+    /// distinguishing "no such user" from "wrong password".
+    pub async fn authenticate(&self, username: &str, password: &str) -> bool {
+        match self.backend.find_user(username).await {
+            Ok(Some(user)) => user.password_hash.verify(password),
+            _ => false,
+        }
+    }
+
+    pub async fn add_ticket(&self, draft: TicketDraft) -> anyhow::Result<TicketId> {
+        let id = self.backend.insert(&draft, Status::ToDo).await?;
         let ticket = Ticket {
             id,
-            title: ticket.title,
-            description: ticket.description,
+            title: draft.title,
+            description: draft.description,
             status: Status::ToDo,
         };
+        self.cache.write().await.insert(id, Arc::new(RwLock::new(ticket.clone())));
+        let _ = self.events.send(TicketEvent {
+            id,
+            kind: TicketEventKind::Created,
+            ticket,
+        });
+        Ok(id)
+    }
+
+    pub async fn get(&self, id: TicketId) -> anyhow::Result<Option<Arc<RwLock<Ticket>>>> {
+        if let Some(ticket) = self.cache.read().await.get(&id) {
+            return Ok(Some(ticket.clone()));
+        }
+        let Some(ticket) = self.backend.get(id).await? else {
+            return Ok(None);
+        };
         let ticket = Arc::new(RwLock::new(ticket));
-        self.tickets.insert(id, ticket);
-        id
+        self.cache.write().await.insert(id, ticket.clone());
+        Ok(Some(ticket))
+    }
+
+    /// Returns a page of tickets ordered by id, optionally filtered by
+    /// `status`, starting strictly after the `after` cursor. Range-scans the
+    /// backend directly rather than the write-through cache, which may be
+    /// cold for a freshly-started store or one most of whose tickets have
+    /// never been individually fetched; warms the cache with whatever comes
+    /// back, reusing an already-cached entry in place of the one just read
+    /// so the page reflects any in-flight update to it. `next` is the id to
+    /// pass as `after` to fetch the following page, or `None` once
+    /// exhausted.
+    pub async fn list(
+        &self,
+        status: Option<Status>,
+        after: Option<TicketId>,
+        limit: usize,
+    ) -> anyhow::Result<TicketPage> {
+        let fetched = self.backend.list(status, after, limit).await?;
+        let next = if fetched.len() == limit {
+            fetched.last().map(|ticket| ticket.id)
+        } else {
+            None
+        };
+
+        let mut cache = self.cache.write().await;
+        let mut tickets = Vec::with_capacity(fetched.len());
+        for ticket in fetched {
+            let id = ticket.id;
+            let entry = cache
+                .entry(id)
+                .or_insert_with(|| Arc::new(RwLock::new(ticket)))
+                .clone();
+            tickets.push(entry.read().await.clone());
+        }
+
+        Ok(TicketPage { tickets, next })
     }
 
-    pub fn get(&self, id: TicketId) -> Option<Arc<RwLock<Ticket>>> {
-        self.tickets.get(&id).cloned()
+    /// Applies `patch` to the ticket it targets, if it exists, persisting
+    /// the result through the backend and publishing a [`TicketEvent`].
+    /// Returns `patch.id` regardless of whether a matching ticket was
+    /// found, matching the old in-memory behaviour. Builds the patched
+    /// ticket and persists it to the backend before touching the cached
+    /// copy, so a failed backend write never leaves the cache holding
+    /// changes that were never durably saved.
+    pub async fn update(&self, patch: TicketPatch) -> anyhow::Result<TicketId> {
+        if let Some(ticket_lock) = self.get(patch.id).await? {
+            let mut ticket = ticket_lock.write().await;
+            let mut candidate = ticket.clone();
+            if let Some(title) = patch.title {
+                candidate.title = title;
+            }
+            if let Some(description) = patch.description {
+                candidate.description = description;
+            }
+            if let Some(status) = patch.status {
+                candidate.status = status;
+            }
+            self.backend.update(candidate.clone()).await?;
+            *ticket = candidate.clone();
+            drop(ticket);
+
+            let _ = self.events.send(TicketEvent {
+                id: patch.id,
+                kind: TicketEventKind::Updated,
+                ticket: candidate,
+            });
+        }
+        Ok(patch.id)
     }
-    
 }
 
+/// An event published by [`TicketStore`] whenever a ticket is created or
+/// updated, so subscribers can react live instead of polling `Get`.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TicketEvent {
+    pub id: TicketId,
+    pub kind: TicketEventKind,
+    pub ticket: Ticket,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TicketEventKind {
+    Created,
+    Updated,
+}
+
+/// A page of tickets returned by [`TicketStore::list`], with a cursor for
+/// fetching the next page or `None` once the scan is exhausted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicketPage {
+    pub tickets: Vec<Ticket>,
+    pub next: Option<TicketId>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Ticket {
     pub id: TicketId,
     pub title: TicketTitle,
@@ -60,9 +197,21 @@ pub struct TicketPatch {
     pub status: Option<Status>,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct TicketId(u64);
 
+impl TicketId {
+    /// Reconstructs a `TicketId` from a raw value read back from a storage
+    /// backend (e.g. a SQL row id).
+    pub fn from_raw(value: u64) -> Self {
+        Self(value)
+    }
+
+    pub fn into_raw(self) -> u64 {
+        self.0
+    }
+}
+
 #[derive(Clone, Debug, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Status {
     ToDo,
@@ -111,6 +260,12 @@ fn validate_description(description: &str) -> Result<(), TicketDescriptionError>
     }
 }
 
+impl TicketDescription {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 /// Title
 
 #[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
@@ -152,27 +307,38 @@ fn validate_title(title: &str) -> Result<(), TicketTitleError> {
     }
 }
 
+impl TicketTitle {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
 
+#[cfg(test)]
 pub fn ticket_title() -> TicketTitle {
     valid_title().try_into().unwrap()
 }
 
+#[cfg(test)]
 pub fn ticket_description() -> TicketDescription {
     valid_description().try_into().unwrap()
 }
 
+#[cfg(test)]
 pub fn overly_long_description() -> String {
     "At vero eos et accusamus et iusto odio dignissimos ducimus qui blanditiis praesentium voluptatum deleniti atque corrupti quos dolores et quas molestias excepturi sint occaecati cupiditate non provident, similique sunt in culpa qui officia deserunt mollitia animi, id est laborum et dolorum fuga. Et harum quidem rerum facilis est et expedita distinctio. Nam libero tempore, cum soluta nobis est eligendi optio cumque nihil impedit quo minus id quod maxime placeat facere possimus, omnis voluptas assumenda est, omnis dolor repellendus. Temporibus autem quibusdam et aut officiis debitis aut rerum necessitatibus saepe eveniet ut et voluptates repudiandae sint et molestiae non recusandae. Itaque earum rerum hic tenetur a sapiente delectus, ut aut reiciendis voluptatibus maiores alias consequatur aut perferendis doloribus asperiores repellat.".into()
 }
 
+#[cfg(test)]
 pub fn overly_long_title() -> String {
     "A title that's definitely longer than what should be allowed in a development ticket".into()
 }
 
+#[cfg(test)]
 pub fn valid_title() -> String {
     "A title".into()
 }
 
+#[cfg(test)]
 pub fn valid_description() -> String {
     "A description".into()
 }
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+/// The outcome of a single command, sent back over the wire instead of
+/// letting a malformed request or missing ticket unwind the connection
+/// task. Every command response is one of these, parameterized by its own
+/// success payload.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Response<T> {
+    Ok(T),
+    Err(ApiError),
+}
+
+/// A machine-readable error returned to a client. `code` lets clients branch
+/// on the failure kind without parsing `message`, which is for logs/humans.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("{message}")]
+pub struct ApiError {
+    pub code: ApiErrorCode,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApiErrorCode {
+    /// A lookup (e.g. `Get`) targeted a ticket id that doesn't exist.
+    NotFound,
+    /// The request itself was malformed, e.g. invalid JSON.
+    BadRequest,
+    /// The connection hasn't authenticated, or authentication failed.
+    Unauthorized,
+    /// Something went wrong on the server's side of things.
+    Internal,
+}
+
+impl ApiError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self {
+            code: ApiErrorCode::NotFound,
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            code: ApiErrorCode::BadRequest,
+            message: message.into(),
+        }
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self {
+            code: ApiErrorCode::Unauthorized,
+            message: message.into(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self {
+            code: ApiErrorCode::Internal,
+            message: message.into(),
+        }
+    }
+}
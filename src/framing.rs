@@ -0,0 +1,73 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest payload `read_frame` will allocate a buffer for. A peer declaring
+/// a length past this is almost certainly not speaking the protocol in good
+/// faith, so we bail out before allocating rather than trusting whatever it
+/// claims its frame is going to be — this runs ahead of the handshake and
+/// authentication, so it has to hold even against an unauthenticated peer.
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Reads one length-prefixed frame: a 4-byte big-endian length followed by
+/// that many bytes. Returns `None` on a clean EOF between frames (the peer
+/// closed the connection) and propagates the error if EOF lands mid-frame.
+/// Rejects a declared length greater than [`MAX_FRAME_SIZE`] without
+/// allocating a buffer for it.
+pub async fn read_frame(reader: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len_buf = [0u8; 4];
+    let mut filled = 0;
+    while filled < len_buf.len() {
+        let n = reader.read(&mut len_buf[filled..]).await?;
+        if n == 0 {
+            if filled == 0 {
+                return Ok(None);
+            }
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        filled += n;
+    }
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("frame length {len} exceeds maximum of {MAX_FRAME_SIZE} bytes"),
+        ));
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+    Ok(Some(payload))
+}
+
+/// Writes one length-prefixed frame and flushes it.
+pub async fn write_frame(
+    writer: &mut (impl AsyncWrite + Unpin),
+    payload: &[u8],
+) -> std::io::Result<()> {
+    writer.write_all(&(payload.len() as u32).to_be_bytes()).await?;
+    writer.write_all(payload).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn round_trips_a_frame() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, b"hello").await.unwrap();
+
+        let mut cursor = Cursor::new(buf);
+        let payload = read_frame(&mut cursor).await.unwrap().unwrap();
+        assert_eq!(payload, b"hello");
+    }
+
+    #[tokio::test]
+    async fn rejects_an_oversized_length_prefix_without_allocating() {
+        let len_buf = (MAX_FRAME_SIZE as u32 + 1).to_be_bytes();
+        let mut cursor = Cursor::new(len_buf.to_vec());
+
+        let err = read_frame(&mut cursor).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -9,19 +9,36 @@
 // Use Rust's package registry, crates.io, to find the dependencies you need
 // (if any) to build this system.
 
-use tokio::net::TcpListener;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::io::{AsyncRead, AsyncWrite};
 use std::net::SocketAddr;
 use std::str;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::Mutex;
+use futures::stream::{self, Stream};
+mod auth;
 mod data;
+mod error;
+mod framing;
+mod protocol;
+mod storage;
+mod transport;
+use auth::Credentials;
 use data::*;
+use error::{ApiError, Response};
+use framing::{read_frame, write_frame};
+use protocol::*;
+use storage::{InMemoryStorage, SqliteStorage, Storage};
+use transport::{negotiate_transport, Framed};
 use serde::{Serialize, Deserialize};
 
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 enum Command {
+    Authenticate {
+        username: String,
+        password: String,
+    },
     Insert {
         draft: TicketDraft,
     },
@@ -31,127 +48,364 @@ enum Command {
     Update {
         patch: TicketPatch,
     },
+    Subscribe {
+        filter: Option<Status>,
+    },
+    List {
+        status: Option<Status>,
+        after: Option<TicketId>,
+        limit: usize,
+    },
+}
+
+/// Which [`Storage`] backend a server should persist tickets to.
+#[derive(Clone, Debug)]
+pub enum StorageBackend {
+    /// Nothing is persisted across restarts; used in tests.
+    InMemory,
+    /// Tickets are persisted to a SQLite database at `database_url`.
+    Sqlite { database_url: String },
 }
 
 pub async fn ticket_server(first: TcpListener) -> Result<(), anyhow::Error> {
-    let handle1 = tokio::spawn(ticket_handler(first));
+    ticket_server_with_backend(first, StorageBackend::InMemory).await
+}
+
+pub async fn ticket_server_with_backend(
+    first: TcpListener,
+    backend: StorageBackend,
+) -> Result<(), anyhow::Error> {
+    let storage: Arc<dyn Storage> = match backend {
+        StorageBackend::InMemory => Arc::new(InMemoryStorage::new()),
+        StorageBackend::Sqlite { database_url } => {
+            Arc::new(SqliteStorage::connect(&database_url).await?)
+        }
+    };
+    ticket_server_with_store(first, Arc::new(TicketStore::new(storage))).await
+}
+
+/// Runs the server against an already-constructed store. Lets callers (and
+/// tests) seed users or tickets before any connection is accepted.
+pub async fn ticket_server_with_store(
+    first: TcpListener,
+    store: Arc<TicketStore>,
+) -> Result<(), anyhow::Error> {
+    let handle1 = tokio::spawn(ticket_handler(first, store));
     handle1.await.unwrap()
 }
 
-async fn ticket_handler(listener: TcpListener) -> Result<(), anyhow::Error> {
-    let store = TicketStore::new();
-    let store = Arc::new(RwLock::new(store));
+/// Wraps a backend failure as a generic `Internal` [`Response`], without
+/// forwarding the backend's own error text (SQL fragments, file paths,
+/// driver-specific detail) to the client. The original error is logged to
+/// stderr first so it isn't lost entirely, just kept off the wire.
+fn internal_error<T>(err: anyhow::Error) -> Response<T> {
+    eprintln!("internal error handling command: {err:#}");
+    Response::Err(ApiError::internal("an internal error occurred"))
+}
+
+/// Capabilities this build is able to honour, advertised by both client and
+/// server in their `Hello`. The handshake negotiates these down to the
+/// intersection with whatever the peer advertises, so either side can still
+/// talk to an older peer that supports fewer of them.
+fn supported_capabilities() -> Vec<Capability> {
+    vec![Capability::Compression, Capability::Encryption]
+}
+
+/// Writes a single JSON value as one length-prefixed frame.
+async fn write_json_frame<T: Serialize>(
+    writer: &mut (impl AsyncWrite + Unpin),
+    value: &T,
+) -> Result<(), anyhow::Error> {
+    let payload = serde_json::to_vec(value)?;
+    write_frame(writer, &payload).await?;
+    Ok(())
+}
+
+/// Reads one length-prefixed frame and deserializes it as JSON. Returns
+/// `None` if the peer closed the connection before sending a frame.
+async fn read_json_frame<T: serde::de::DeserializeOwned>(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> Result<Option<T>, anyhow::Error> {
+    match read_frame(reader).await? {
+        Some(payload) => Ok(Some(serde_json::from_slice(&payload)?)),
+        None => Ok(None),
+    }
+}
+
+/// Client side of the handshake: advertise our capabilities, then either
+/// accept the server's negotiated `Hello` or bail out on a version mismatch.
+async fn perform_handshake(
+    reader: &mut (impl AsyncRead + Unpin),
+    writer: &mut (impl AsyncWrite + Unpin),
+) -> Result<Vec<Capability>, anyhow::Error> {
+    let hello = Hello::new(supported_capabilities());
+    write_json_frame(writer, &hello).await?;
+
+    match read_json_frame(reader).await? {
+        Some(HandshakeResponse::Accepted(server_hello)) => Ok(server_hello.capabilities),
+        Some(HandshakeResponse::Rejected(rejected)) => {
+            Err(anyhow::anyhow!("handshake rejected: {}", rejected.reason))
+        }
+        None => Err(anyhow::anyhow!("connection closed during handshake")),
+    }
+}
+
+async fn ticket_handler(listener: TcpListener, store: Arc<TicketStore>) -> Result<(), anyhow::Error> {
     loop {
-        let (mut socket, _) = listener.accept().await?;
+        let (socket, _) = listener.accept().await?;
         let store_client = store.clone();
-        let response_handle = tokio::spawn(async move {
-            let (mut reader, mut writer) = socket.split();
-            let mut buf: Vec<u8> = Vec::new();
-            reader.read_to_end(&mut buf).await.unwrap();
-
-            let request: Command = serde_json::from_slice(&buf).unwrap();
-
-            let response = match request {
-                Command::Insert {draft}=> {
-                    let id = store_client.write().await.add_ticket(draft);
-                    let response = serde_json::to_vec(&id);
-                    response
-                },
-                Command::Get  {id}=> {
-                    let store_reader = store_client.read().await;
-                    let ticket = store_reader.get(id).unwrap();
-                    let ticket = ticket.read().await;
-                    let response = serde_json::to_vec(&ticket.clone());
-                    response
-                },
-                Command::Update{patch} => {
-                    let store_reader = store_client.read().await;
-                    if let Some(ticket_locked) = store_reader.get(patch.id) {
-                        let mut ticket = ticket_locked.write().await;
-                        if let Some(title) = patch.title {
-                            ticket.title = title;
-                        }
-                        if let Some(description) = patch.description {
-                            ticket.description = description;
-                        }
-                        if let Some(status) = patch.status {
-                            ticket.status = status;
-                        }
-                    }
-                    let response = serde_json::to_vec(&patch.id);
-                    response
-                }
+        let our_capabilities = supported_capabilities();
+        tokio::spawn(handle_connection(socket, store_client, our_capabilities));
+    }
+}
+
+/// Drives a single client connection end to end: negotiates the handshake
+/// once, then loops over framed commands for as long as the peer keeps the
+/// connection open, issuing one framed response per command. A `Subscribe`
+/// command gets no response; the connection streams events from then on.
+async fn handle_connection(
+    mut socket: TcpStream,
+    store: Arc<TicketStore>,
+    our_capabilities: Vec<Capability>,
+) -> Result<(), anyhow::Error> {
+    let agreed = {
+        let (mut reader, mut writer) = socket.split();
+
+        let client_hello: Hello = match read_json_frame(&mut reader).await? {
+            Some(hello) => hello,
+            None => return Ok(()),
+        };
+
+        if client_hello.version.major != ProtocolVersion::CURRENT.major {
+            let rejection = HandshakeResponse::Rejected(HandshakeRejected {
+                reason: format!(
+                    "unsupported protocol major version {} (server speaks {})",
+                    client_hello.version.major,
+                    ProtocolVersion::CURRENT.major
+                ),
+            });
+            write_json_frame(&mut writer, &rejection).await?;
+            return Ok(());
+        }
+
+        let agreed = negotiate_capabilities(&our_capabilities, &client_hello.capabilities);
+        let server_hello = Hello {
+            version: ProtocolVersion::CURRENT,
+            capabilities: agreed.clone(),
+        };
+        write_json_frame(&mut writer, &HandshakeResponse::Accepted(server_hello)).await?;
+        agreed
+    };
+
+    let mut transport = negotiate_transport(socket, &agreed).await?;
+
+    // Connections start unauthenticated; only `Authenticate` is allowed
+    // until a session is established, after which it stays established for
+    // the rest of the (now persistent) connection.
+    let mut authenticated_as: Option<String> = None;
+
+    while let Some(payload) = transport.recv().await? {
+        let request: Command = match serde_json::from_slice(&payload) {
+            Ok(request) => request,
+            Err(e) => {
+                let response: Response<()> =
+                    Response::Err(ApiError::bad_request(format!("malformed command: {e}")));
+                transport.send(&serde_json::to_vec(&response)?).await?;
+                continue;
+            }
+        };
+
+        match request {
+            Command::Authenticate { username, password } => {
+                let response: Response<bool> = if store.authenticate(&username, &password).await {
+                    authenticated_as = Some(username);
+                    Response::Ok(true)
+                } else {
+                    Response::Err(ApiError::unauthorized("invalid username or password"))
+                };
+                transport.send(&serde_json::to_vec(&response)?).await?;
+            }
+            other if authenticated_as.is_none() => {
+                let _ = other;
+                let response: Response<()> =
+                    Response::Err(ApiError::unauthorized("authentication required"));
+                transport.send(&serde_json::to_vec(&response)?).await?;
+            }
+            Command::Insert {draft}=> {
+                let response = match store.add_ticket(draft).await {
+                    Ok(id) => Response::Ok(id),
+                    Err(e) => internal_error(e),
+                };
+                transport.send(&serde_json::to_vec(&response)?).await?;
+            },
+            Command::Get  {id}=> {
+                let response = match store.get(id).await {
+                    Ok(Some(ticket)) => Response::Ok(ticket.read().await.clone()),
+                    Ok(None) => Response::Err(ApiError::not_found(format!(
+                        "no ticket with id {}",
+                        id.into_raw()
+                    ))),
+                    Err(e) => internal_error(e),
+                };
+                transport.send(&serde_json::to_vec(&response)?).await?;
+            },
+            Command::Update{patch} => {
+                let response = match store.update(patch).await {
+                    Ok(id) => Response::Ok(id),
+                    Err(e) => internal_error(e),
+                };
+                transport.send(&serde_json::to_vec(&response)?).await?;
+            },
+            Command::List { status, after, limit } => {
+                let response = match store.list(status, after, limit).await {
+                    Ok(page) => Response::Ok(page),
+                    Err(e) => internal_error(e),
+                };
+                transport.send(&serde_json::to_vec(&response)?).await?;
+            },
+            Command::Subscribe { filter } => {
+                // Dedicates the rest of this connection to streaming events;
+                // no further commands are read off it afterwards.
+                return stream_subscription(&mut *transport, store.subscribe(), filter).await;
+            }
+        };
+    }
 
-            }.unwrap();
+    Ok(())
+}
 
-            //println!("Responded!");
-            writer.write_all(&response).await.unwrap();
-        });
+/// Forwards ticket events matching `filter` to `transport` as they are
+/// published, until the publisher side is dropped or a send fails.
+async fn stream_subscription(
+    transport: &mut dyn Framed,
+    mut events: tokio::sync::broadcast::Receiver<TicketEvent>,
+    filter: Option<Status>,
+) -> Result<(), anyhow::Error> {
+    use tokio::sync::broadcast::error::RecvError;
 
-        response_handle.await.unwrap();
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                if filter.is_none_or(|status| event.ticket.status == status) {
+                    transport.send(&serde_json::to_vec(&event)?).await?;
+                }
+            }
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return Ok(()),
+        }
     }
 }
 
-#[derive(Clone, Copy, Debug,)]
+/// A client connection to the ticket server. Holds a single persistent,
+/// framed `TcpStream` behind a mutex so many `insert`/`get`/`update` calls
+/// reuse it instead of opening a fresh socket per command. Calls take turns:
+/// each holds the mutex from sending its command until its response
+/// arrives, so commands are not pipelined.
+#[derive(Clone)]
 pub struct TicketClient {
-    addr: SocketAddr,
+    transport: Arc<Mutex<Box<dyn Framed>>>,
 }
 
 impl TicketClient {
-    pub fn new(addr: SocketAddr) -> Self {
-        TicketClient {
-            addr
-        }
-    }
-    pub async fn insert(self, draft: TicketDraft) -> TicketId {
-        let req = Command::Insert {
-            draft
+    /// Connects to `addr`, performs the protocol handshake, negotiates the
+    /// transport (plain, compressed, encrypted, or both) both peers agreed
+    /// on, then logs in with `credentials` before handing back a client
+    /// ready to issue commands over the now-authenticated connection.
+    pub async fn connect(addr: SocketAddr, credentials: Credentials) -> Result<Self, anyhow::Error> {
+        let mut stream = TcpStream::connect(addr).await?;
+        let agreed = {
+            let (mut reader, mut writer) = stream.split();
+            perform_handshake(&mut reader, &mut writer).await?
         };
-        let mut socket = tokio::net::TcpStream::connect(self.addr).await.unwrap();
-        let (mut reader, mut writer) = socket.split();
-    
-        let request_formatted = serde_json::to_vec(&req).unwrap();
-        writer.write_all(&request_formatted).await.unwrap();
-        writer.shutdown().await.unwrap();
-    
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf).await.unwrap();
-        let response_formatted: TicketId = serde_json::from_slice(&buf).unwrap();
-        response_formatted
-    }
-    
-    pub async fn get(self, id: TicketId) -> Ticket {
-        let req = Command::Get {
-            id
+        let transport = negotiate_transport(stream, &agreed).await?;
+        let client = TicketClient {
+            transport: Arc::new(Mutex::new(transport)),
         };
-        let mut socket = tokio::net::TcpStream::connect(self.addr).await.unwrap();
-        let (mut reader, mut writer) = socket.split();
-    
-        let request_formatted = serde_json::to_vec(&req).unwrap();
-        writer.write_all(&request_formatted).await.unwrap();
-        writer.shutdown().await.unwrap();
-        //println!("Requested with {:#?}", &id);
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf).await.unwrap();
-        let response_formatted: Ticket = serde_json::from_slice(&buf).unwrap();
-        response_formatted
+        client.login(credentials).await?;
+        Ok(client)
     }
-    
-    pub async fn update(self, patch: TicketPatch) -> TicketId {
-        let req = Command::Update {
-            patch
+
+    /// Sends an `Authenticate` command and waits for the server to confirm
+    /// the session, returning an error if the credentials were rejected.
+    async fn login(&self, credentials: Credentials) -> Result<(), anyhow::Error> {
+        let command = Command::Authenticate {
+            username: credentials.username,
+            password: credentials.password,
         };
-        let mut socket = tokio::net::TcpStream::connect(self.addr).await.unwrap();
-        let (mut reader, mut writer) = socket.split();
-    
-        let request_formatted = serde_json::to_vec(&req).unwrap();
-        writer.write_all(&request_formatted).await.unwrap();
-        writer.shutdown().await.unwrap();
-        //println!("Requested with {:#?}", &id);
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf).await.unwrap();
-        let response_formatted: TicketId = serde_json::from_slice(&buf).unwrap();
-        response_formatted
+        let mut transport = self.transport.lock().await;
+
+        let payload = serde_json::to_vec(&command)?;
+        transport.send(&payload).await?;
+
+        let response = transport
+            .recv()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("connection closed during authentication"))?;
+        let response: Response<bool> = serde_json::from_slice(&response)?;
+        match response {
+            Response::Ok(true) => Ok(()),
+            Response::Ok(false) => Err(anyhow::anyhow!("authentication failed")),
+            Response::Err(err) => Err(anyhow::anyhow!("authentication failed: {}", err.message)),
+        }
+    }
+
+    async fn call<Resp: serde::de::DeserializeOwned>(
+        &self,
+        command: &Command,
+    ) -> Result<Resp, ApiError> {
+        let mut transport = self.transport.lock().await;
+
+        let payload = serde_json::to_vec(command).unwrap();
+        transport.send(&payload).await.unwrap();
+
+        let response = transport.recv().await.unwrap().unwrap();
+        match serde_json::from_slice(&response).unwrap() {
+            Response::Ok(value) => Ok(value),
+            Response::Err(err) => Err(err),
+        }
+    }
+
+    pub async fn insert(self, draft: TicketDraft) -> Result<TicketId, ApiError> {
+        self.call(&Command::Insert { draft }).await
+    }
+
+    pub async fn get(self, id: TicketId) -> Result<Ticket, ApiError> {
+        self.call(&Command::Get { id }).await
+    }
+
+    pub async fn update(self, patch: TicketPatch) -> Result<TicketId, ApiError> {
+        self.call(&Command::Update { patch }).await
+    }
+
+    /// Fetches a page of up to `limit` tickets matching `status`, starting
+    /// strictly after the `after` cursor. Pass `page.next` back as `after`
+    /// to fetch the following page.
+    pub async fn list(
+        self,
+        status: Option<Status>,
+        after: Option<TicketId>,
+        limit: usize,
+    ) -> Result<TicketPage, ApiError> {
+        self.call(&Command::List { status, after, limit }).await
+    }
+
+    /// Sends a `Subscribe` command and returns a stream of ticket events
+    /// matching `filter`. This dedicates the client's connection to the
+    /// subscription, mirroring the server's handling of `Subscribe`, so
+    /// `self` is consumed rather than borrowed.
+    pub async fn subscribe(
+        self,
+        filter: Option<Status>,
+    ) -> std::pin::Pin<Box<dyn Stream<Item = TicketEvent> + Send>> {
+        let mut transport = self.transport.lock_owned().await;
+        let payload = serde_json::to_vec(&Command::Subscribe { filter }).unwrap();
+        transport.send(&payload).await.unwrap();
+
+        Box::pin(stream::unfold(transport, |mut transport| async move {
+            let payload = transport.recv().await.ok()??;
+            let event: TicketEvent = serde_json::from_slice(&payload).ok()?;
+            Some((event, transport))
+        }))
     }
 }
 
@@ -159,6 +413,7 @@ impl TicketClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use futures::StreamExt;
     use std::net::SocketAddr;
     use std::panic;
     use tokio::task::JoinSet;
@@ -169,12 +424,32 @@ mod tests {
         (listener, addr)
     }
 
+    fn test_credentials() -> Credentials {
+        Credentials {
+            username: "alice".to_string(),
+            password: "hunter2".to_string(),
+        }
+    }
+
+    /// Binds a listener and starts a server with a single registered user,
+    /// so tests can authenticate with [`test_credentials`].
+    async fn bind_random_with_user() -> (SocketAddr, Arc<TicketStore>) {
+        let (listener, addr) = bind_random().await;
+        let store = Arc::new(TicketStore::new(Arc::new(InMemoryStorage::new())));
+        let credentials = test_credentials();
+        store
+            .create_user(credentials.username, &credentials.password)
+            .await
+            .unwrap();
+        tokio::spawn(ticket_server_with_store(listener, store.clone()));
+        (addr, store)
+    }
+
     #[tokio::test]
     async fn test_insert_get_tickets() {
-        let (listener, addr) = bind_random().await;
-        tokio::spawn(ticket_server(listener));
+        let (addr, _store) = bind_random_with_user().await;
         // server is running, begin sending tasks
-        let client = TicketClient::new(addr);
+        let client = TicketClient::connect(addr, test_credentials()).await.unwrap();
 
         let draft1 =  TicketDraft {
             title: ticket_title(),
@@ -187,8 +462,8 @@ mod tests {
         };
         // issue insert requests
         let mut insert_join_set = JoinSet::new();
-        insert_join_set.spawn(client.insert(draft1));
-        insert_join_set.spawn(client.insert(draft2));
+        insert_join_set.spawn(client.clone().insert(draft1));
+        insert_join_set.spawn(client.clone().insert(draft2));
 
         let mut ticket_ids = Vec::new();
         while let Some(outcome) = insert_join_set.join_next().await {
@@ -198,7 +473,7 @@ mod tests {
                         panic::resume_unwind(reason);
                     }
                 },
-                Ok(val) => ticket_ids.push(val), 
+                Ok(val) => ticket_ids.push(val.unwrap()), 
             }
         }
         // println!("Returned with {:#?}", &ticket_ids);
@@ -207,8 +482,8 @@ mod tests {
         let ticket_id2 = ticket_ids[1];
         // issue get requests
         let mut get_join_set = JoinSet::new();
-        get_join_set.spawn(client.get(ticket_id1));
-        get_join_set.spawn(client.get(ticket_id2));
+        get_join_set.spawn(client.clone().get(ticket_id1));
+        get_join_set.spawn(client.clone().get(ticket_id2));
 
         let mut tickets: Vec<Ticket> = Vec::new();
         while let Some(outcome) = get_join_set.join_next().await {
@@ -218,7 +493,7 @@ mod tests {
                         panic::resume_unwind(reason);
                     }
                 },
-                Ok(val) => tickets.push(val),
+                Ok(val) => tickets.push(val.unwrap()),
             }
         }
 
@@ -232,10 +507,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_update_tickets() {
-        let (listener, addr) = bind_random().await;
-        tokio::spawn(ticket_server(listener));
+        let (addr, _store) = bind_random_with_user().await;
 
-        let client = TicketClient::new(addr);
+        let client = TicketClient::connect(addr, test_credentials()).await.unwrap();
 
         let draft1 =  TicketDraft {
             title: ticket_title(),
@@ -244,7 +518,7 @@ mod tests {
 
         // issue insert requests
         let mut insert_join_set = JoinSet::new();
-        insert_join_set.spawn(client.insert(draft1));
+        insert_join_set.spawn(client.clone().insert(draft1));
 
         let mut ticket_ids = Vec::new();
         while let Some(outcome) = insert_join_set.join_next().await {
@@ -254,7 +528,7 @@ mod tests {
                         panic::resume_unwind(reason);
                     }
                 },
-                Ok(val) => ticket_ids.push(val), 
+                Ok(val) => ticket_ids.push(val.unwrap()), 
             }
         }
         // println!("Returned with {:#?}", &ticket_ids);
@@ -262,7 +536,7 @@ mod tests {
         let ticket_id1 = ticket_ids[0];
 
         let ticket_patch1 = TicketPatch {
-            id: ticket_id1.clone(),
+            id: ticket_id1,
             title: Some(TicketTitle::try_from("Modified").unwrap()),
             description: Some(TicketDescription::try_from("Modified as well").unwrap()),
             status: Some(Status::InProgress)
@@ -270,7 +544,7 @@ mod tests {
 
         // issue update requests
         let mut update_join_set = JoinSet::new();
-        update_join_set.spawn(client.update(ticket_patch1));
+        update_join_set.spawn(client.clone().update(ticket_patch1));
 
         let mut ticket_ids2: Vec<TicketId> = Vec::new();
         while let Some(outcome) = update_join_set.join_next().await {
@@ -280,7 +554,7 @@ mod tests {
                         panic::resume_unwind(reason);
                     }
                 },
-                Ok(val) => ticket_ids2.push(val),
+                Ok(val) => ticket_ids2.push(val.unwrap()),
             }
         }
         // println!("Returned with {:#?}", &ticket_ids2);
@@ -288,7 +562,7 @@ mod tests {
 
         // issue get requests
         let mut get_join_set = JoinSet::new();
-        get_join_set.spawn(client.get(ticket_id1));
+        get_join_set.spawn(client.clone().get(ticket_id1));
 
         let mut patched_tickets: Vec<Ticket> = Vec::new();
         while let Some(outcome) = get_join_set.join_next().await {
@@ -298,7 +572,7 @@ mod tests {
                         panic::resume_unwind(reason);
                     }
                 },
-                Ok(val) => patched_tickets.push(val),
+                Ok(val) => patched_tickets.push(val.unwrap()),
             }
         }
 
@@ -309,4 +583,121 @@ mod tests {
 
     }
 
+    #[tokio::test]
+    async fn handshake_negotiates_compression_and_encryption() {
+        let (addr, _store) = bind_random_with_user().await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        let agreed = {
+            let (mut reader, mut writer) = stream.split();
+            perform_handshake(&mut reader, &mut writer).await.unwrap()
+        };
+
+        assert!(agreed.contains(&Capability::Compression));
+        assert!(agreed.contains(&Capability::Encryption));
+    }
+
+    #[tokio::test]
+    async fn test_insert_get_over_negotiated_transport() {
+        // `bind_random_with_user` + `TicketClient::connect` both advertise
+        // the full capability set, so this exercises `CompressedFramed`
+        // wrapping `EncryptedFramed` end to end, not just `PlainFramed`.
+        let (addr, _store) = bind_random_with_user().await;
+        let client = TicketClient::connect(addr, test_credentials()).await.unwrap();
+
+        let draft = TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        };
+        let id = client.clone().insert(draft).await.unwrap();
+        let ticket = client.clone().get(id).await.unwrap();
+
+        assert_eq!(ticket.id, id);
+        assert_eq!(ticket.title, ticket_title());
+    }
+
+    #[tokio::test]
+    async fn test_list_tickets_paginated() {
+        let (addr, _store) = bind_random_with_user().await;
+        let client = TicketClient::connect(addr, test_credentials()).await.unwrap();
+
+        for _ in 0..3 {
+            let draft = TicketDraft {
+                title: ticket_title(),
+                description: ticket_description(),
+            };
+            client.clone().insert(draft).await.unwrap();
+        }
+
+        let first_page = client.clone().list(None, None, 2).await.unwrap();
+        assert_eq!(first_page.tickets.len(), 2);
+        assert!(first_page.next.is_some());
+
+        let second_page = client.clone().list(None, first_page.next, 2).await.unwrap();
+        assert_eq!(second_page.tickets.len(), 1);
+        assert_eq!(second_page.next, None);
+    }
+
+    #[tokio::test]
+    async fn test_list_sees_tickets_persisted_before_a_restart() {
+        let backend: Arc<dyn Storage> =
+            Arc::new(SqliteStorage::connect("sqlite::memory:?cache=shared").await.unwrap());
+        let first_store = Arc::new(TicketStore::new(backend.clone()));
+        first_store
+            .add_ticket(TicketDraft {
+                title: ticket_title(),
+                description: ticket_description(),
+            })
+            .await
+            .unwrap();
+        first_store
+            .add_ticket(TicketDraft {
+                title: ticket_title(),
+                description: ticket_description(),
+            })
+            .await
+            .unwrap();
+
+        // A freshly constructed `TicketStore` has an empty cache, the same
+        // as a server that just restarted against the same persisted
+        // backend; `list` still has to find both tickets.
+        let restarted_store = TicketStore::new(backend);
+        let page = restarted_store.list(None, None, 10).await.unwrap();
+        assert_eq!(page.tickets.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_receives_filtered_events() {
+        let (addr, _store) = bind_random_with_user().await;
+        // `Subscribe` dedicates a connection's remaining lifetime to
+        // streaming events, so issue it over its own connection rather than
+        // the one used to insert/update, mirroring how a real client would
+        // split the two roles.
+        let commands = TicketClient::connect(addr, test_credentials()).await.unwrap();
+        let subscriber = TicketClient::connect(addr, test_credentials()).await.unwrap();
+
+        let mut events = subscriber.subscribe(Some(Status::InProgress)).await;
+
+        let draft = TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        };
+        let id = commands.clone().insert(draft).await.unwrap();
+
+        let patch = TicketPatch {
+            id,
+            title: None,
+            description: None,
+            status: Some(Status::InProgress),
+        };
+        commands.clone().update(patch).await.unwrap();
+
+        // Creation is published as `ToDo`, which the subscriber's filter
+        // excludes; only the update to `InProgress` should arrive.
+        let event = events.next().await.unwrap();
+        assert_eq!(event.id, id);
+        assert_eq!(event.kind, TicketEventKind::Updated);
+        assert_eq!(event.ticket.status, Status::InProgress);
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+/// Semantic version of the wire protocol spoken by a peer.
+///
+/// Peers are compatible as long as `major` matches; `minor`/`patch` are
+/// informational for now but let us evolve the handshake without bumping
+/// `major` for purely additive changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl ProtocolVersion {
+    pub const CURRENT: ProtocolVersion = ProtocolVersion {
+        major: 1,
+        minor: 0,
+        patch: 0,
+    };
+}
+
+/// Optional wire-level features a peer is willing to use.
+///
+/// The handshake negotiates a shared set up front. `Compression` and
+/// `Encryption` decide the transport used for the rest of the connection
+/// (see `transport::negotiate_transport`); `Subscribe` is not advertised by
+/// this build, and the `Subscribe` command does not depend on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Capability {
+    Compression,
+    Encryption,
+    Subscribe,
+}
+
+/// First frame sent by either peer right after the connection is established.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hello {
+    pub version: ProtocolVersion,
+    pub capabilities: Vec<Capability>,
+}
+
+impl Hello {
+    pub fn new(capabilities: Vec<Capability>) -> Self {
+        Self {
+            version: ProtocolVersion::CURRENT,
+            capabilities,
+        }
+    }
+}
+
+/// Sent by the server instead of a `Hello` when the handshake cannot proceed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandshakeRejected {
+    pub reason: String,
+}
+
+/// The server's reply to a client `Hello`: either acceptance, carrying the
+/// server's own `Hello`, or a structured rejection.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HandshakeResponse {
+    Accepted(Hello),
+    Rejected(HandshakeRejected),
+}
+
+/// Capabilities both peers advertised, i.e. the ones safe to rely on for the
+/// rest of the connection.
+pub fn negotiate_capabilities(ours: &[Capability], theirs: &[Capability]) -> Vec<Capability> {
+    let theirs: HashSet<&Capability> = theirs.iter().collect();
+    ours.iter().filter(|c| theirs.contains(c)).copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiate_keeps_only_shared_capabilities() {
+        let ours = vec![Capability::Compression, Capability::Subscribe];
+        let theirs = vec![Capability::Subscribe, Capability::Encryption];
+        assert_eq!(
+            negotiate_capabilities(&ours, &theirs),
+            vec![Capability::Subscribe]
+        );
+    }
+
+    #[test]
+    fn negotiate_empty_when_nothing_shared() {
+        let ours = vec![Capability::Compression];
+        let theirs = vec![Capability::Encryption];
+        assert!(negotiate_capabilities(&ours, &theirs).is_empty());
+    }
+}
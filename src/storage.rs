@@ -0,0 +1,364 @@
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+
+use crate::auth::{PasswordHashRecord, User};
+use crate::data::{Status, Ticket, TicketDescription, TicketDraft, TicketId, TicketTitle};
+
+/// Durable storage for tickets and users, so a `TicketStore` survives a
+/// restart. Implementations are picked per deployment: in-memory for tests,
+/// SQLite in production.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Allocates a fresh id for a new ticket built from `draft` and `status`
+    /// and inserts it, as a single atomic operation so two concurrent
+    /// inserts can never be handed the same id.
+    async fn insert(&self, draft: &TicketDraft, status: Status) -> anyhow::Result<TicketId>;
+    async fn get(&self, id: TicketId) -> anyhow::Result<Option<Ticket>>;
+    async fn update(&self, ticket: Ticket) -> anyhow::Result<()>;
+
+    /// Returns up to `limit` tickets ordered by id, optionally filtered by
+    /// `status`, starting strictly after the `after` cursor. Backs
+    /// [`crate::data::TicketStore::list`]'s keyset pagination, so it has to
+    /// work against a store that was just restarted with a cold cache.
+    async fn list(
+        &self,
+        status: Option<Status>,
+        after: Option<TicketId>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Ticket>>;
+
+    async fn create_user(&self, user: User) -> anyhow::Result<()>;
+    async fn find_user(&self, username: &str) -> anyhow::Result<Option<User>>;
+}
+
+/// Keeps every ticket and user in memory. Used in tests and as the default
+/// backend when nothing durable is configured.
+#[derive(Default)]
+pub struct InMemoryStorage {
+    tickets: RwLock<BTreeMap<TicketId, Ticket>>,
+    counter: AtomicU64,
+    users: RwLock<HashMap<String, User>>,
+}
+
+impl InMemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Storage for InMemoryStorage {
+    async fn insert(&self, draft: &TicketDraft, status: Status) -> anyhow::Result<TicketId> {
+        let id = TicketId::from_raw(self.counter.fetch_add(1, Ordering::SeqCst));
+        let ticket = Ticket {
+            id,
+            title: draft.title.clone(),
+            description: draft.description.clone(),
+            status,
+        };
+        self.tickets.write().await.insert(id, ticket);
+        Ok(id)
+    }
+
+    async fn get(&self, id: TicketId) -> anyhow::Result<Option<Ticket>> {
+        Ok(self.tickets.read().await.get(&id).cloned())
+    }
+
+    async fn update(&self, ticket: Ticket) -> anyhow::Result<()> {
+        self.tickets.write().await.insert(ticket.id, ticket);
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        status: Option<Status>,
+        after: Option<TicketId>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Ticket>> {
+        use std::ops::Bound;
+
+        let lower = match after {
+            Some(id) => Bound::Excluded(id),
+            None => Bound::Unbounded,
+        };
+
+        let tickets = self.tickets.read().await;
+        Ok(tickets
+            .range((lower, Bound::Unbounded))
+            .map(|(_, ticket)| ticket)
+            .filter(|ticket| status.is_none_or(|status| ticket.status == status))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn create_user(&self, user: User) -> anyhow::Result<()> {
+        self.users.write().await.insert(user.username.clone(), user);
+        Ok(())
+    }
+
+    async fn find_user(&self, username: &str) -> anyhow::Result<Option<User>> {
+        Ok(self.users.read().await.get(username).cloned())
+    }
+}
+
+/// Persists tickets in a SQLite `tickets` table. Ids are assigned by SQLite
+/// itself (an `INTEGER PRIMARY KEY` column behaves as a rowid alias that
+/// auto-increments from the largest existing id), so allocation and
+/// insertion happen as one atomic statement and keep incrementing across
+/// restarts without us tracking a counter.
+pub struct SqliteStorage {
+    pool: sqlx::SqlitePool,
+}
+
+impl SqliteStorage {
+    pub async fn connect(database_url: &str) -> anyhow::Result<Self> {
+        let pool = sqlx::SqlitePool::connect(database_url).await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS tickets (
+                id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                description TEXT NOT NULL,
+                status TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS users (
+                username TEXT PRIMARY KEY,
+                password_hash TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn insert(&self, draft: &TicketDraft, status: Status) -> anyhow::Result<TicketId> {
+        let (id,): (i64,) = sqlx::query_as(
+            "INSERT INTO tickets (title, description, status) VALUES (?, ?, ?) RETURNING id",
+        )
+        .bind(draft.title.as_str())
+        .bind(draft.description.as_str())
+        .bind(status_to_str(status))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(TicketId::from_raw(id as u64))
+    }
+
+    async fn get(&self, id: TicketId) -> anyhow::Result<Option<Ticket>> {
+        let row = sqlx::query_as::<_, TicketRow>(
+            "SELECT id, title, description, status FROM tickets WHERE id = ?",
+        )
+        .bind(id.into_raw() as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(TicketRow::into_ticket).transpose()
+    }
+
+    async fn update(&self, ticket: Ticket) -> anyhow::Result<()> {
+        sqlx::query("UPDATE tickets SET title = ?, description = ?, status = ? WHERE id = ?")
+            .bind(ticket.title.as_str())
+            .bind(ticket.description.as_str())
+            .bind(status_to_str(ticket.status))
+            .bind(ticket.id.into_raw() as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        status: Option<Status>,
+        after: Option<TicketId>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Ticket>> {
+        // `id`s are never negative, so `-1` as a sentinel for "no cursor"
+        // lets both branches share the same `id > ?` comparison.
+        let after_id = after.map_or(-1, |id| id.into_raw() as i64);
+        // SQLite treats a negative `LIMIT` as "no limit", so a `limit` that
+        // overflows `i64` (e.g. `usize::MAX`, which wraps to `-1`) must clamp
+        // to `i64::MAX` rather than silently turning into an unbounded scan.
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let rows = match status {
+            Some(status) => {
+                sqlx::query_as::<_, TicketRow>(
+                    "SELECT id, title, description, status FROM tickets
+                     WHERE id > ? AND status = ?
+                     ORDER BY id
+                     LIMIT ?",
+                )
+                .bind(after_id)
+                .bind(status_to_str(status))
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query_as::<_, TicketRow>(
+                    "SELECT id, title, description, status FROM tickets
+                     WHERE id > ?
+                     ORDER BY id
+                     LIMIT ?",
+                )
+                .bind(after_id)
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+        rows.into_iter().map(TicketRow::into_ticket).collect()
+    }
+
+    async fn create_user(&self, user: User) -> anyhow::Result<()> {
+        sqlx::query("INSERT INTO users (username, password_hash) VALUES (?, ?)")
+            .bind(&user.username)
+            .bind(user.password_hash.as_str())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn find_user(&self, username: &str) -> anyhow::Result<Option<User>> {
+        let row = sqlx::query_as::<_, UserRow>(
+            "SELECT username, password_hash FROM users WHERE username = ?",
+        )
+        .bind(username)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(UserRow::into_user).transpose()
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct UserRow {
+    username: String,
+    password_hash: String,
+}
+
+impl UserRow {
+    fn into_user(self) -> anyhow::Result<User> {
+        Ok(User {
+            username: self.username,
+            password_hash: PasswordHashRecord::from_phc_string(self.password_hash),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TicketRow {
+    id: i64,
+    title: String,
+    description: String,
+    status: String,
+}
+
+impl TicketRow {
+    fn into_ticket(self) -> anyhow::Result<Ticket> {
+        Ok(Ticket {
+            id: TicketId::from_raw(self.id as u64),
+            title: TicketTitle::try_from(self.title)?,
+            description: TicketDescription::try_from(self.description)?,
+            status: status_from_str(&self.status)?,
+        })
+    }
+}
+
+fn status_to_str(status: Status) -> &'static str {
+    match status {
+        Status::ToDo => "todo",
+        Status::InProgress => "in_progress",
+        Status::Done => "done",
+    }
+}
+
+fn status_from_str(value: &str) -> anyhow::Result<Status> {
+    match value {
+        "todo" => Ok(Status::ToDo),
+        "in_progress" => Ok(Status::InProgress),
+        "done" => Ok(Status::Done),
+        other => Err(anyhow::anyhow!("unknown ticket status `{other}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+    use tokio::task::JoinSet;
+
+    fn a_draft() -> TicketDraft {
+        TicketDraft {
+            title: TicketTitle::try_from("A title").unwrap(),
+            description: TicketDescription::try_from("A description").unwrap(),
+        }
+    }
+
+    async fn assert_concurrent_inserts_get_distinct_ids(storage: impl Storage + 'static) {
+        let storage = Arc::new(storage);
+        let mut joins = JoinSet::new();
+        for _ in 0..20 {
+            let storage = storage.clone();
+            joins.spawn(async move { storage.insert(&a_draft(), Status::ToDo).await.unwrap() });
+        }
+
+        let mut ids = HashSet::new();
+        while let Some(id) = joins.join_next().await {
+            assert!(ids.insert(id.unwrap()), "two concurrent inserts were given the same id");
+        }
+        assert_eq!(ids.len(), 20);
+    }
+
+    #[tokio::test]
+    async fn in_memory_concurrent_inserts_get_distinct_ids() {
+        assert_concurrent_inserts_get_distinct_ids(InMemoryStorage::new()).await;
+    }
+
+    #[tokio::test]
+    async fn sqlite_concurrent_inserts_get_distinct_ids() {
+        let storage = SqliteStorage::connect("sqlite::memory:?cache=shared").await.unwrap();
+        assert_concurrent_inserts_get_distinct_ids(storage).await;
+    }
+
+    #[tokio::test]
+    async fn sqlite_round_trips_a_ticket() {
+        let storage = SqliteStorage::connect("sqlite::memory:?cache=shared").await.unwrap();
+        let draft = a_draft();
+
+        let id = storage.insert(&draft, Status::ToDo).await.unwrap();
+        let ticket = storage.get(id).await.unwrap().unwrap();
+
+        assert_eq!(ticket.title, draft.title);
+        assert_eq!(ticket.description, draft.description);
+        assert_eq!(ticket.status, Status::ToDo);
+    }
+
+    #[tokio::test]
+    async fn sqlite_survives_a_reconnect_to_the_same_database() {
+        // A bare `:memory:` DSN mints a fresh, uniquely-named database on
+        // every `connect()` call even with `cache=shared` set, so two
+        // independent `SqliteStorage`s would each get their own empty
+        // database. Naming the in-memory database explicitly is what
+        // actually lets `cache=shared` pool connections onto the same one.
+        let database_url = "file:sqlite_survives_a_reconnect_to_the_same_database?mode=memory&cache=shared";
+        let first = SqliteStorage::connect(database_url).await.unwrap();
+        let id = first.insert(&a_draft(), Status::ToDo).await.unwrap();
+
+        // A second `SqliteStorage` against the same URL stands in for a
+        // server restart: its pool is unrelated to `first`'s, so this only
+        // finds the ticket if it was actually persisted to the database.
+        let second = SqliteStorage::connect(database_url).await.unwrap();
+        let ticket = second.get(id).await.unwrap().unwrap();
+        assert_eq!(ticket.title, a_draft().title);
+    }
+}
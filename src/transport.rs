@@ -0,0 +1,254 @@
+use std::io;
+
+use async_trait::async_trait;
+use chacha20poly1305::aead::{Aead, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce};
+use tokio::io::{AsyncRead, AsyncWrite};
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey};
+
+use crate::framing::{read_frame, write_frame};
+use crate::protocol::Capability;
+
+/// A connection-scoped byte transport, abstracting over whether frames are
+/// sent plain, compressed, encrypted, or both. Command handling talks only
+/// to this trait and never needs to know which of those is in effect.
+#[async_trait]
+pub trait Framed: Send {
+    async fn send(&mut self, payload: &[u8]) -> io::Result<()>;
+    async fn recv(&mut self) -> io::Result<Option<Vec<u8>>>;
+}
+
+#[async_trait]
+impl Framed for Box<dyn Framed> {
+    async fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+        (**self).send(payload).await
+    }
+
+    async fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        (**self).recv().await
+    }
+}
+
+/// Plain length-prefixed framing with no compression or encryption; the
+/// fallback when neither capability was negotiated.
+pub struct PlainFramed<S> {
+    stream: S,
+}
+
+impl<S> PlainFramed<S> {
+    pub fn new(stream: S) -> Self {
+        Self { stream }
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRead + AsyncWrite + Send + Unpin> Framed for PlainFramed<S> {
+    async fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+        write_frame(&mut self.stream, payload).await
+    }
+
+    async fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        read_frame(&mut self.stream).await
+    }
+}
+
+/// Wraps an inner [`Framed`] transport, deflate-compressing every payload
+/// before it reaches the inner transport and decompressing it again on the
+/// way back out.
+pub struct CompressedFramed<F> {
+    inner: F,
+}
+
+impl<F: Framed> CompressedFramed<F> {
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl<F: Framed> Framed for CompressedFramed<F> {
+    async fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+        use std::io::Write;
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(payload)?;
+        let compressed = encoder.finish()?;
+        self.inner.send(&compressed).await
+    }
+
+    async fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        use std::io::Read;
+        let Some(compressed) = self.inner.recv().await? else {
+            return Ok(None);
+        };
+        let mut decoder = flate2::read::DeflateDecoder::new(&compressed[..]);
+        let mut payload = Vec::new();
+        decoder.read_to_end(&mut payload)?;
+        Ok(Some(payload))
+    }
+}
+
+/// Wraps an inner [`Framed`] transport with ChaCha20-Poly1305 AEAD, keyed by
+/// a shared secret derived from an X25519 Diffie-Hellman exchange performed
+/// once at connection setup. Each side tracks its own outgoing/incoming
+/// nonce counter so every frame gets a fresh nonce.
+pub struct EncryptedFramed<F> {
+    inner: F,
+    cipher: ChaCha20Poly1305,
+    send_nonce: u64,
+    recv_nonce: u64,
+}
+
+impl<F: Framed> EncryptedFramed<F> {
+    pub fn new(inner: F, shared_secret: &[u8; 32]) -> Self {
+        Self {
+            inner,
+            cipher: ChaCha20Poly1305::new(shared_secret.into()),
+            send_nonce: 0,
+            recv_nonce: 0,
+        }
+    }
+
+    /// ChaCha20-Poly1305 nonces are 96 bits; we zero-pad a per-message
+    /// counter into the low 64 bits so nonces never repeat for the lifetime
+    /// of a connection.
+    fn nonce_for(counter: u64) -> Nonce {
+        let mut bytes = [0u8; 12];
+        bytes[4..].copy_from_slice(&counter.to_be_bytes());
+        *Nonce::from_slice(&bytes)
+    }
+}
+
+#[async_trait]
+impl<F: Framed> Framed for EncryptedFramed<F> {
+    async fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+        let nonce = Self::nonce_for(self.send_nonce);
+        self.send_nonce += 1;
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, payload)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "encryption failed"))?;
+        self.inner.send(&ciphertext).await
+    }
+
+    async fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let Some(ciphertext) = self.inner.recv().await? else {
+            return Ok(None);
+        };
+        let nonce = Self::nonce_for(self.recv_nonce);
+        self.recv_nonce += 1;
+        let payload = self
+            .cipher
+            .decrypt(&nonce, ciphertext.as_ref())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "decryption failed"))?;
+        Ok(Some(payload))
+    }
+}
+
+/// Performs an X25519 key exchange over an already-framed but not yet
+/// encrypted transport: each side sends its ephemeral public key as a raw
+/// frame, then derives the shared secret from the peer's reply. Symmetric,
+/// so client and server call this the same way.
+async fn exchange_x25519_secret(framed: &mut dyn Framed) -> Result<[u8; 32], anyhow::Error> {
+    let secret = EphemeralSecret::random_from_rng(OsRng);
+    let public = X25519PublicKey::from(&secret);
+
+    framed.send(public.as_bytes()).await?;
+    let their_public = framed
+        .recv()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("connection closed during key exchange"))?;
+    let their_public: [u8; 32] = their_public
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("malformed X25519 public key"))?;
+
+    Ok(*secret
+        .diffie_hellman(&X25519PublicKey::from(their_public))
+        .as_bytes())
+}
+
+/// Builds the transport both peers agreed on: wraps `stream` in plain
+/// framing, then layers encryption and/or compression on top depending on
+/// which capabilities were negotiated in the handshake. Falls back to plain
+/// framing when neither was agreed.
+pub async fn negotiate_transport<S>(
+    stream: S,
+    agreed: &[Capability],
+) -> Result<Box<dyn Framed>, anyhow::Error>
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let mut plain = PlainFramed::new(stream);
+
+    let mut transport: Box<dyn Framed> = if agreed.contains(&Capability::Encryption) {
+        let secret = exchange_x25519_secret(&mut plain).await?;
+        Box::new(EncryptedFramed::new(plain, &secret))
+    } else {
+        Box::new(plain)
+    };
+
+    if agreed.contains(&Capability::Compression) {
+        transport = Box::new(CompressedFramed::new(transport));
+    }
+
+    Ok(transport)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    fn paired_plain() -> (PlainFramed<tokio::io::DuplexStream>, PlainFramed<tokio::io::DuplexStream>) {
+        let (a, b) = duplex(4096);
+        (PlainFramed::new(a), PlainFramed::new(b))
+    }
+
+    #[tokio::test]
+    async fn compressed_framed_round_trips_a_payload() {
+        let (a, b) = paired_plain();
+        let mut a = CompressedFramed::new(a);
+        let mut b = CompressedFramed::new(b);
+
+        a.send(b"hello, compressed world").await.unwrap();
+        let received = b.recv().await.unwrap().unwrap();
+
+        assert_eq!(received, b"hello, compressed world");
+    }
+
+    #[tokio::test]
+    async fn encrypted_framed_round_trips_a_payload() {
+        let (a, b) = paired_plain();
+        let secret = [7u8; 32];
+        let mut a = EncryptedFramed::new(a, &secret);
+        let mut b = EncryptedFramed::new(b, &secret);
+
+        a.send(b"hello, encrypted world").await.unwrap();
+        let received = b.recv().await.unwrap().unwrap();
+
+        assert_eq!(received, b"hello, encrypted world");
+    }
+
+    #[tokio::test]
+    async fn encrypted_framed_rejects_a_payload_under_the_wrong_secret() {
+        let (a, b) = paired_plain();
+        let mut a = EncryptedFramed::new(a, &[1u8; 32]);
+        let mut b = EncryptedFramed::new(b, &[2u8; 32]);
+
+        a.send(b"hello").await.unwrap();
+        assert!(b.recv().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn x25519_exchange_agrees_on_a_shared_secret() {
+        let (a, b) = duplex(4096);
+        let mut a: Box<dyn Framed> = Box::new(PlainFramed::new(a));
+        let mut b: Box<dyn Framed> = Box::new(PlainFramed::new(b));
+
+        let (secret_a, secret_b) =
+            tokio::join!(exchange_x25519_secret(&mut *a), exchange_x25519_secret(&mut *b));
+
+        assert_eq!(secret_a.unwrap(), secret_b.unwrap());
+    }
+}